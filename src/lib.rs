@@ -2,6 +2,12 @@
 //!
 //! This is mainly intended as a tool for build scripts which need to use LLVM
 //! but don't want to manually parse the output and handle errors every time.
+//!
+//! The free functions at the crate root (e.g. [`version()`], [`libs()`])
+//! operate on a lazily-discovered default [`LlvmConfig`]. If you need to
+//! point at a specific installation - say, because the system ships a
+//! version-suffixed binary like `llvm-config-17`, or because you want to
+//! pin an exact path - construct an [`LlvmConfig`] yourself.
 
 #![forbid(unsafe_code)]
 #![deny(missing_docs, missing_debug_implementations)]
@@ -10,93 +16,804 @@ use std::{
     ffi::OsStr,
     fmt::{self, Display, Formatter},
     io,
-    path::PathBuf,
-    process::{Command, Output, Stdio},
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus, Output, Stdio},
     string::FromUtf8Error,
+    sync::OnceLock,
 };
 
-/// Print LLVM version.
-pub fn version() -> Result<String, Error> {
-    map_stdout(&["--verson"], ToString::to_string)
+/// The environment variables which, if set, pin the exact `llvm-config`
+/// binary to use, bypassing discovery entirely.
+const ENV_OVERRIDES: &[&str] = &["LLVM_CONFIG", "LLVM_CONFIG_PATH"];
+
+/// Whether to prefer statically or dynamically linking against LLVM.
+///
+/// Mirrors `llvm-config`'s `--link-static`/`--link-shared` flags, which
+/// change what the `--libs`/`--ldflags`/`--libfiles` queries emit.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum LinkMode {
+    /// Let `llvm-config` decide, based on how LLVM itself was built.
+    #[default]
+    Auto,
+    /// Pass `--link-static`.
+    Static,
+    /// Pass `--link-shared`.
+    Shared,
+}
+
+impl LinkMode {
+    /// The `llvm-config` flag this mode corresponds to, if any.
+    fn flag(self) -> Option<&'static str> {
+        match self {
+            LinkMode::Auto => None,
+            LinkMode::Static => Some("--link-static"),
+            LinkMode::Shared => Some("--link-shared"),
+        }
+    }
+}
+
+/// A parsed LLVM version, e.g. `16.0.2` or `17.0.0git`.
+///
+/// Implements [`Ord`] so callers can gate version-specific behaviour:
+///
+/// ```rust,no_run
+/// # use llvm_config::Version;
+/// # fn cfg() -> Result<Version, llvm_config::Error> { unimplemented!() }
+/// if cfg()? >= Version::new(16, 0, 0) {
+///     // use an API only available from LLVM 16 onwards
+/// }
+/// # Ok::<(), llvm_config::Error>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    /// The major version, e.g. `16` in `16.0.2`.
+    pub major: u32,
+    /// The minor version, e.g. `0` in `16.0.2`.
+    pub minor: u32,
+    /// The patch version, e.g. `2` in `16.0.2`.
+    pub patch: u32,
+    /// Anything trailing the `major.minor.patch` triple, e.g. `git` in
+    /// `17.0.0git`.
+    pub suffix: String,
+}
+
+impl Version {
+    /// Construct a [`Version`] with no suffix.
+    pub fn new(major: u32, minor: u32, patch: u32) -> Self {
+        Version {
+            major,
+            minor,
+            patch,
+            suffix: String::new(),
+        }
+    }
+
+    /// Parse the output of `llvm-config --version`.
+    fn parse(raw: &str) -> Result<Self, Error> {
+        let raw = raw.trim();
+        let bad_version = || Error::UnparseableVersion { raw: raw.to_string() };
+
+        let mut parts = raw.splitn(3, '.');
+        let major: u32 = parts.next().ok_or_else(bad_version)?.parse().map_err(|_| bad_version())?;
+        let minor: u32 = parts.next().unwrap_or("0").parse().map_err(|_| bad_version())?;
+        let rest = parts.next().unwrap_or("0");
+
+        let digits = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+        let (patch, suffix) = rest.split_at(digits);
+        let patch: u32 = patch.parse().map_err(|_| bad_version())?;
+
+        Ok(Version {
+            major,
+            minor,
+            patch,
+            suffix: suffix.to_string(),
+        })
+    }
+}
+
+impl Display for Version {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}{}", self.major, self.minor, self.patch, self.suffix)
+    }
+}
+
+/// A coarse classification of a target triple's CPU architecture.
+///
+/// Following the machine-type mapping rustc's own LLVM archive builder uses,
+/// this lets downstream code branch on architecture (e.g. to pick a
+/// prebuilt archive or a set of linker flags) without re-parsing triples
+/// itself.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Arch {
+    /// `x86_64`/`amd64`.
+    X86_64,
+    /// 32-bit x86 (`i386`, `i686`, ...).
+    X86,
+    /// `aarch64`/`arm64`.
+    Aarch64,
+    /// 32-bit ARM (`arm`, `armv7`, `thumbv7neon`, ...).
+    Arm,
+    /// PowerPC64 (`powerpc64`, `powerpc64le`).
+    PowerPc64,
+    /// 64-bit RISC-V (`riscv64`, `riscv64gc`).
+    Riscv64,
+    /// A target whose architecture component isn't recognised.
+    Other,
+}
+
+impl Arch {
+    /// Classify the architecture component of a target triple, e.g.
+    /// `x86_64-unknown-linux-gnu` -> [`Arch::X86_64`].
+    pub fn classify(target_triple: &str) -> Self {
+        match target_triple.split('-').next().unwrap_or(target_triple) {
+            "x86_64" | "amd64" => Arch::X86_64,
+            "i386" | "i486" | "i586" | "i686" | "x86" => Arch::X86,
+            "aarch64" | "arm64" => Arch::Aarch64,
+            "powerpc64" | "powerpc64le" | "ppc64" | "ppc64le" => Arch::PowerPc64,
+            "riscv64" | "riscv64gc" => Arch::Riscv64,
+            arch if arch.starts_with("arm") || arch.starts_with("thumb") => Arch::Arm,
+            _ => Arch::Other,
+        }
+    }
+}
+
+/// A resolved `llvm-config` binary.
+///
+/// Every query method (`version()`, `libs()`, ...) shells out to this
+/// binary, reusing the resolved path so callers only pay the discovery cost
+/// once. Construct one with [`LlvmConfig::new()`] to use the default
+/// discovery strategy, [`LlvmConfig::builder()`] to customise it, or
+/// [`LlvmConfig::at()`] to pin an exact path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LlvmConfig {
+    binary: PathBuf,
+    link_mode: LinkMode,
+}
+
+impl LlvmConfig {
+    /// The oldest version-suffixed binary name ([`LlvmConfigBuilder::discover()`]
+    /// will probe) before giving up, e.g. `llvm-config-8`.
+    pub const MIN_VERSION: u32 = 8;
+    /// The newest version-suffixed binary name [`LlvmConfigBuilder::discover()`]
+    /// will probe, e.g. `llvm-config-18`.
+    pub const MAX_VERSION: u32 = 18;
+
+    /// Locate an `llvm-config` binary using the default discovery strategy.
+    ///
+    /// See [`LlvmConfigBuilder::discover()`] for the rules used.
+    pub fn new() -> Result<Self, Error> {
+        LlvmConfig::builder().discover()
+    }
+
+    /// Start building a customised [`LlvmConfig`], e.g. to search a specific
+    /// directory or try a caller-supplied list of binary names.
+    pub fn builder() -> LlvmConfigBuilder {
+        LlvmConfigBuilder::default()
+    }
+
+    /// Use an exact path to an `llvm-config`-compatible binary, skipping
+    /// discovery entirely.
+    pub fn at(binary: impl Into<PathBuf>) -> Self {
+        LlvmConfig {
+            binary: binary.into(),
+            link_mode: LinkMode::default(),
+        }
+    }
+
+    /// The resolved `llvm-config` binary this [`LlvmConfig`] will invoke.
+    pub fn binary(&self) -> &Path {
+        &self.binary
+    }
+
+    /// Prefer static or shared linking for link-related queries (`libs()`,
+    /// `ldflags()`, `libfiles()`, ...), or let `llvm-config` decide.
+    pub fn with_link_mode(mut self, link_mode: LinkMode) -> Self {
+        self.link_mode = link_mode;
+        self
+    }
+
+    /// The [`LinkMode`] link-related queries are run with.
+    pub fn link_mode(&self) -> LinkMode {
+        self.link_mode
+    }
+
+    /// Whether this LLVM installation prefers linking shared or static
+    /// libraries by default (`llvm-config --shared-mode`).
+    ///
+    /// Always returns [`LinkMode::Static`] or [`LinkMode::Shared`], never
+    /// [`LinkMode::Auto`].
+    pub fn shared_mode(&self) -> Result<LinkMode, Error> {
+        self.map_stdout(&["--shared-mode"], |s| match s.trim() {
+            "shared" => LinkMode::Shared,
+            _ => LinkMode::Static,
+        })
+    }
+
+    /// The parsed LLVM version.
+    pub fn version(&self) -> Result<Version, Error> {
+        let output = self.run(&["--version"])?;
+        let stdout = String::from_utf8(output.stdout)?;
+        Version::parse(&stdout)
+    }
+
+    /// The target triple LLVM itself was built for.
+    pub fn host_target(&self) -> Result<String, Error> {
+        self.map_stdout(&["--host-target"], |s| s.to_string())
+    }
+
+    /// The architecture LLVM was built for, classified from
+    /// [`LlvmConfig::host_target()`].
+    pub fn host_arch(&self) -> Result<Arch, Error> {
+        Ok(Arch::classify(&self.host_target()?))
+    }
+
+    /// The backends enabled in this LLVM build (e.g. `X86`, `AArch64`, ...).
+    pub fn targets_built(&self) -> Result<impl Iterator<Item = String>, Error> {
+        self.stdout_words(&["--targets-built"])
+    }
+
+    /// Whether LLVM was built in `Debug`, `Release`, or `RelWithDebInfo` mode.
+    pub fn build_mode(&self) -> Result<String, Error> {
+        self.map_stdout(&["--build-mode"], |s| s.to_string())
+    }
+
+    /// Whether this LLVM build has assertions enabled.
+    pub fn assertion_mode(&self) -> Result<bool, Error> {
+        self.map_stdout(&["--assertion-mode"], |s| s.trim().eq_ignore_ascii_case("on"))
+    }
+
+    /// Print the installation prefix.
+    pub fn prefix(&self) -> Result<PathBuf, Error> {
+        self.map_stdout(&["--prefix"], |s| PathBuf::from(s))
+    }
+
+    /// Print the source root LLVM was built from.
+    pub fn src_root(&self) -> Result<PathBuf, Error> {
+        self.map_stdout(&["--src-root"], |s| PathBuf::from(s))
+    }
+
+    /// Print the object root used to build LLVM.
+    pub fn obj_root(&self) -> Result<PathBuf, Error> {
+        self.map_stdout(&["--obj-root"], |s| PathBuf::from(s))
+    }
+
+    /// Directory containing LLVM executables.
+    pub fn bin_dir(&self) -> Result<PathBuf, Error> {
+        self.map_stdout(&["--bin-dir"], |s| PathBuf::from(s))
+    }
+
+    /// Directory containing LLVM headers.
+    pub fn include_dir(&self) -> Result<PathBuf, Error> {
+        self.map_stdout(&["--include-dir"], |s| PathBuf::from(s))
+    }
+
+    /// Directory containing LLVM libraries.
+    pub fn lib_dir(&self) -> Result<PathBuf, Error> {
+        self.map_stdout(&["--lib-dir"], |s| PathBuf::from(s))
+    }
+
+    /// Directory containing LLVM cmake modules.
+    pub fn cmake_dir(&self) -> Result<PathBuf, Error> {
+        self.map_stdout(&["--cmake-dir"], |s| PathBuf::from(s))
+    }
+
+    /// C preprocessor flags for files that include LLVM headers.
+    pub fn cpp_flags(&self) -> Result<impl Iterator<Item = String>, Error> {
+        self.stdout_words(&["--cppflags"])
+    }
+
+    /// C compiler flags for files that include LLVM headers.
+    pub fn c_flags(&self) -> Result<impl Iterator<Item = String>, Error> {
+        self.stdout_words(&["--cflags"])
+    }
+
+    /// C++ compiler flags for files that include LLVM headers.
+    pub fn cxx_flags(&self) -> Result<impl Iterator<Item = String>, Error> {
+        self.stdout_words(&["--cxxflags"])
+    }
+
+    /// Print Linker flags.
+    pub fn ldflags(&self) -> Result<impl Iterator<Item = String>, Error> {
+        self.stdout_words(self.linked_args(["--ldflags"]))
+    }
+
+    /// System Libraries needed to link against LLVM components.
+    pub fn system_libs(&self) -> Result<impl Iterator<Item = String>, Error> {
+        self.stdout_words(self.linked_args(["--system-libs"]))
+    }
+
+    /// Libraries needed to link against LLVM components.
+    pub fn libs(&self) -> Result<impl Iterator<Item = String>, Error> {
+        self.stdout_words(self.linked_args(["--libs"]))
+    }
+
+    /// Bare library names for in-tree builds.
+    pub fn libnames(&self) -> Result<String, Error> {
+        self.map_stdout(self.linked_args(["--libnames"]), |s| String::from(s))
+    }
+
+    /// Fully qualified library filenames for makefile depends.
+    pub fn libfiles(&self) -> Result<impl Iterator<Item = String>, Error> {
+        self.stdout_words(self.linked_args(["--libfiles"]))
+    }
+
+    /// Prepend the flag for the current [`LinkMode`] (if any) to some
+    /// link-related arguments.
+    fn linked_args<const N: usize>(&self, args: [&str; N]) -> Vec<String> {
+        let mut full = Vec::with_capacity(N + 1);
+        if let Some(flag) = self.link_mode.flag() {
+            full.push(flag.to_string());
+        }
+        full.extend(args.iter().map(|s| s.to_string()));
+        full
+    }
+
+    /// List of all possible components.
+    pub fn components(&self) -> Result<impl Iterator<Item = String>, Error> {
+        self.stdout_words(&["--components"])
+    }
+
+    /// Libraries needed to link against the given LLVM components, with
+    /// transitive dependencies resolved (e.g. `libs_for(["core", "x86"])`
+    /// pulls in everything `core` and `x86` depend on).
+    ///
+    /// Returns [`Error::UnknownComponent`] if a name isn't one
+    /// [`LlvmConfig::components()`] recognises.
+    pub fn libs_for<I, S>(&self, components: I) -> Result<impl Iterator<Item = String>, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let args = self.component_args("--libs", components)?;
+        self.stdout_words(args)
+    }
+
+    /// System libraries needed to link against the given LLVM components. See
+    /// [`LlvmConfig::libs_for()`] for details.
+    pub fn system_libs_for<I, S>(&self, components: I) -> Result<impl Iterator<Item = String>, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let args = self.component_args("--system-libs", components)?;
+        self.stdout_words(args)
+    }
+
+    /// Fully qualified library filenames for the given LLVM components. See
+    /// [`LlvmConfig::libs_for()`] for details.
+    pub fn libfiles_for<I, S>(&self, components: I) -> Result<impl Iterator<Item = String>, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let args = self.component_args("--libfiles", components)?;
+        self.stdout_words(args)
+    }
+
+    /// Bare library names for the given LLVM components. See
+    /// [`LlvmConfig::libs_for()`] for details.
+    pub fn libnames_for<I, S>(&self, components: I) -> Result<String, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let args = self.component_args("--libnames", components)?;
+        self.map_stdout(args, |s| String::from(s))
+    }
+
+    /// Validate `components` against [`LlvmConfig::components()`] and build
+    /// the argument vector `flag component1 component2 ...` used by the
+    /// `*_for` queries.
+    fn component_args<I, S>(&self, flag: &str, components: I) -> Result<Vec<String>, Error>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let requested: Vec<String> = components
+            .into_iter()
+            .map(|name| name.as_ref().to_string())
+            .collect();
+        let available: Vec<String> = self.components()?.collect();
+
+        for name in &requested {
+            if !available.contains(name) {
+                return Err(Error::UnknownComponent {
+                    name: name.clone(),
+                    available: available.clone(),
+                });
+            }
+        }
+
+        let mut args = self.linked_args([flag]);
+        args.extend(requested);
+        Ok(args)
+    }
+
+    /// Emit the `cargo:rustc-link-search` and `cargo:rustc-link-lib`
+    /// directives needed to link against LLVM, derived from
+    /// [`LlvmConfig::lib_dir()`], [`LlvmConfig::libs()`], and
+    /// [`LlvmConfig::system_libs()`].
+    ///
+    /// This is meant to be called directly from a build script, removing the
+    /// need to hand-translate query results into `cargo:` directives:
+    ///
+    /// ```rust,no_run
+    /// llvm_config::LlvmConfig::new()
+    ///     .and_then(|cfg| cfg.emit_cargo_metadata())
+    ///     .expect("Unable to link against LLVM");
+    /// ```
+    pub fn emit_cargo_metadata(&self) -> Result<(), Error> {
+        println!("cargo:rustc-link-search=native={}", self.lib_dir()?.display());
+
+        let kind = match self.link_mode {
+            LinkMode::Static => "static=",
+            LinkMode::Shared => "dylib=",
+            LinkMode::Auto => "",
+        };
+
+        for lib in self.libs()?.chain(self.system_libs()?) {
+            println!("cargo:rustc-link-lib={}{}", kind, normalize_lib_name(&lib));
+        }
+
+        Ok(())
+    }
+
+    /// Invoke the resolved `llvm-config` binary with some arguments.
+    fn run<I, O>(&self, args: I) -> Result<Output, Error>
+    where
+        I: IntoIterator<Item = O>,
+        O: AsRef<OsStr>,
+    {
+        let args: Vec<String> = args
+            .into_iter()
+            .map(|arg| arg.as_ref().to_string_lossy().into_owned())
+            .collect();
+
+        let mut command = Command::new(&self.binary);
+        command.stdin(Stdio::null());
+        command.args(&args);
+
+        let output = command.output().map_err(Error::UnableToInvoke)?;
+
+        if output.status.success() {
+            Ok(output)
+        } else {
+            Err(Error::BadExitCode(CommandFailure {
+                binary: self.binary.clone(),
+                args,
+                status: output.status,
+                stderr: String::from_utf8_lossy(&output.stderr).into_owned(),
+            }))
+        }
+    }
+
+    /// Invoke `llvm-config` then transform STDOUT.
+    fn map_stdout<I, O, F, T>(&self, args: I, map: F) -> Result<T, Error>
+    where
+        I: IntoIterator<Item = O>,
+        O: AsRef<OsStr>,
+        F: FnOnce(&str) -> T,
+    {
+        let output = self.run(args)?;
+        let stdout = String::from_utf8(output.stdout)?;
+        Ok(map(stdout.trim()))
+    }
+
+    /// Invoke `llvm-config` then split STDOUT by spaces.
+    fn stdout_words<I, O>(&self, args: I) -> Result<impl Iterator<Item = String>, Error>
+    where
+        I: IntoIterator<Item = O>,
+        O: AsRef<OsStr>,
+    {
+        let output = self.run(args)?;
+        let stdout = String::from_utf8(output.stdout)?;
+        Ok(SpaceSeparatedStrings::new(stdout))
+    }
+}
+
+/// Builds an [`LlvmConfig`] by searching for a suitable `llvm-config` binary.
+///
+/// Discovery, in order:
+///
+/// 1. if the `LLVM_CONFIG` or `LLVM_CONFIG_PATH` environment variable is
+///    set, it's used verbatim and no further searching happens
+/// 2. otherwise, each name added with [`LlvmConfigBuilder::candidate()`] (or,
+///    if none were added, a default sweep of `llvm-config`,
+///    `llvm-config-18`, ..., down to `llvm-config-8`) is tried, both inside
+///    the directory set with [`LlvmConfigBuilder::search_dir()`] (if any)
+///    and on `$PATH`
+#[derive(Debug, Default, Clone)]
+pub struct LlvmConfigBuilder {
+    candidates: Vec<String>,
+    search_dir: Option<PathBuf>,
+    link_mode: LinkMode,
+}
+
+impl LlvmConfigBuilder {
+    /// Add a binary name to try. Once any name has been added, only the
+    /// added names are tried and the default sweep of version-suffixed names
+    /// is skipped.
+    pub fn candidate(mut self, name: impl Into<String>) -> Self {
+        self.candidates.push(name.into());
+        self
+    }
+
+    /// Also look for candidates inside this directory (e.g. the `bin/`
+    /// directory of an LLVM installation prefix).
+    pub fn search_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.search_dir = Some(dir.into());
+        self
+    }
+
+    /// Prefer static or shared linking for link-related queries on the
+    /// resulting [`LlvmConfig`]. See [`LlvmConfig::with_link_mode()`].
+    pub fn link_mode(mut self, link_mode: LinkMode) -> Self {
+        self.link_mode = link_mode;
+        self
+    }
+
+    /// Run discovery and return the first `llvm-config` binary found.
+    pub fn discover(self) -> Result<LlvmConfig, Error> {
+        if let Some(binary) = env_override() {
+            return Ok(LlvmConfig { binary, link_mode: self.link_mode });
+        }
+
+        let candidates = if self.candidates.is_empty() {
+            default_candidate_names()
+        } else {
+            self.candidates
+        };
+
+        let mut tried = Vec::new();
+
+        for name in &candidates {
+            if let Some(dir) = &self.search_dir {
+                let candidate = dir.join(name);
+                if is_runnable(&candidate) {
+                    return Ok(LlvmConfig { binary: candidate, link_mode: self.link_mode });
+                }
+                tried.push(candidate.display().to_string());
+            }
+
+            let candidate = PathBuf::from(name);
+            if is_runnable(&candidate) {
+                return Ok(LlvmConfig { binary: candidate, link_mode: self.link_mode });
+            }
+            tried.push(name.clone());
+        }
+
+        Err(Error::NotFound { tried })
+    }
+}
+
+/// Check the `LLVM_CONFIG`/`LLVM_CONFIG_PATH` environment variables for an
+/// explicit override.
+fn env_override() -> Option<PathBuf> {
+    ENV_OVERRIDES.iter().find_map(|var| {
+        let value = std::env::var_os(var)?;
+        if value.is_empty() {
+            None
+        } else {
+            Some(PathBuf::from(value))
+        }
+    })
+}
+
+/// The default sweep of binary names to try, newest version first.
+fn default_candidate_names() -> Vec<String> {
+    let mut names = vec![String::from("llvm-config")];
+    names.extend(
+        (LlvmConfig::MIN_VERSION..=LlvmConfig::MAX_VERSION)
+            .rev()
+            .map(|version| format!("llvm-config-{}", version)),
+    );
+    names
+}
+
+/// Try to spawn `binary --version`, treating anything other than "the OS
+/// couldn't find this executable" as evidence the binary exists and runs.
+fn is_runnable(binary: &Path) -> bool {
+    match Command::new(binary)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+    {
+        Ok(_) => true,
+        Err(e) => e.kind() != io::ErrorKind::NotFound,
+    }
+}
+
+/// The parsed LLVM version. See [`LlvmConfig::version()`] for details.
+pub fn version() -> Result<Version, Error> {
+    default_config()?.version()
+}
+
+/// The target triple LLVM itself was built for. See
+/// [`LlvmConfig::host_target()`] for details.
+pub fn host_target() -> Result<String, Error> {
+    default_config()?.host_target()
+}
+
+/// The architecture LLVM was built for. See [`LlvmConfig::host_arch()`] for
+/// details.
+pub fn host_arch() -> Result<Arch, Error> {
+    default_config()?.host_arch()
+}
+
+/// The backends enabled in this LLVM build. See
+/// [`LlvmConfig::targets_built()`] for details.
+pub fn targets_built() -> Result<impl Iterator<Item = String>, Error> {
+    default_config()?.targets_built()
+}
+
+/// Whether LLVM was built in `Debug`, `Release`, or `RelWithDebInfo` mode.
+/// See [`LlvmConfig::build_mode()`] for details.
+pub fn build_mode() -> Result<String, Error> {
+    default_config()?.build_mode()
+}
+
+/// Whether this LLVM build has assertions enabled. See
+/// [`LlvmConfig::assertion_mode()`] for details.
+pub fn assertion_mode() -> Result<bool, Error> {
+    default_config()?.assertion_mode()
 }
 
 /// Print the installation prefix.
 pub fn prefix() -> Result<PathBuf, Error> {
-    map_stdout(&["--prefix"], |s| PathBuf::from(s))
+    default_config()?.prefix()
 }
 
 /// Print the source root LLVM was built from.
 pub fn src_root() -> Result<PathBuf, Error> {
-    map_stdout(&["--src-root"], |s| PathBuf::from(s))
+    default_config()?.src_root()
 }
 /// Print the object root used to build LLVM.
 pub fn obj_root() -> Result<PathBuf, Error> {
-    map_stdout(&["--obj-root"], |s| PathBuf::from(s))
+    default_config()?.obj_root()
 }
 
 /// Directory containing LLVM executables.
 pub fn bin_dir() -> Result<PathBuf, Error> {
-    map_stdout(&["--bin-dir"], |s| PathBuf::from(s))
+    default_config()?.bin_dir()
 }
 
 /// Directory containing LLVM headers.
 pub fn include_dir() -> Result<PathBuf, Error> {
-    map_stdout(&["--include-dir"], |s| PathBuf::from(s))
+    default_config()?.include_dir()
 }
 
 /// Directory containing LLVM libraries.
 pub fn lib_dir() -> Result<PathBuf, Error> {
-    map_stdout(&["--lib-dir"], |s| PathBuf::from(s))
+    default_config()?.lib_dir()
 }
 
 /// Directory containing LLVM cmake modules.
 pub fn cmake_dir() -> Result<PathBuf, Error> {
-    map_stdout(&["--cmake-dir"], |s| PathBuf::from(s))
+    default_config()?.cmake_dir()
 }
 
 /// C preprocessor flags for files that include LLVM headers.
 pub fn cpp_flags() -> Result<impl Iterator<Item = String>, Error> {
-    stdout_words(&["--cppflags"])
+    default_config()?.cpp_flags()
 }
 
 /// C compiler flags for files that include LLVM headers.
 pub fn c_flags() -> Result<impl Iterator<Item = String>, Error> {
-    stdout_words(&["--cflags"])
+    default_config()?.c_flags()
 }
 
 /// C++ compiler flags for files that include LLVM headers.
 pub fn cxx_flags() -> Result<impl Iterator<Item = String>, Error> {
-    stdout_words(&["--cxxflags"])
+    default_config()?.cxx_flags()
 }
 
 /// Print Linker flags.
 pub fn ldflags() -> Result<impl Iterator<Item = String>, Error> {
-    stdout_words(&["--ldflags"])
+    default_config()?.ldflags()
 }
 
 /// System Libraries needed to link against LLVM components.
 pub fn system_libs() -> Result<impl Iterator<Item = String>, Error> {
-    stdout_words(&["--system-libs"])
+    default_config()?.system_libs()
 }
 
 /// Libraries needed to link against LLVM components.
 pub fn libs() -> Result<impl Iterator<Item = String>, Error> {
-    stdout_words(&["--libs"])
+    default_config()?.libs()
 }
 
 /// Bare library names for in-tree builds.
 pub fn libnames() -> Result<String, Error> {
-    map_stdout(&["--libnames"], |s| String::from(s))
+    default_config()?.libnames()
 }
 
 /// Fully qualified library filenames for makefile depends.
 pub fn libfiles() -> Result<impl Iterator<Item = String>, Error> {
-    stdout_words(&["--libfiles"])
+    default_config()?.libfiles()
 }
 
 /// List of all possible components.
 pub fn components() -> Result<impl Iterator<Item = String>, Error> {
-    stdout_words(&["--components"])
+    default_config()?.components()
+}
+
+/// Emit the `cargo:` directives needed to link against LLVM. See
+/// [`LlvmConfig::emit_cargo_metadata()`] for details.
+pub fn emit_cargo_metadata() -> Result<(), Error> {
+    default_config()?.emit_cargo_metadata()
+}
+
+/// Whether this LLVM installation prefers linking shared or static
+/// libraries by default. See [`LlvmConfig::shared_mode()`] for details.
+pub fn shared_mode() -> Result<LinkMode, Error> {
+    default_config()?.shared_mode()
+}
+
+/// Libraries needed to link against the given LLVM components. See
+/// [`LlvmConfig::libs_for()`] for details.
+pub fn libs_for<I, S>(components: I) -> Result<impl Iterator<Item = String>, Error>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    default_config()?.libs_for(components)
+}
+
+/// System libraries needed to link against the given LLVM components. See
+/// [`LlvmConfig::system_libs_for()`] for details.
+pub fn system_libs_for<I, S>(components: I) -> Result<impl Iterator<Item = String>, Error>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    default_config()?.system_libs_for(components)
+}
+
+/// Fully qualified library filenames for the given LLVM components. See
+/// [`LlvmConfig::libfiles_for()`] for details.
+pub fn libfiles_for<I, S>(components: I) -> Result<impl Iterator<Item = String>, Error>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    default_config()?.libfiles_for(components)
+}
+
+/// Bare library names for the given LLVM components. See
+/// [`LlvmConfig::libnames_for()`] for details.
+pub fn libnames_for<I, S>(components: I) -> Result<String, Error>
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<str>,
+{
+    default_config()?.libnames_for(components)
+}
+
+/// Strip the `-lfoo` or `foo.lib` decorations `llvm-config` puts around a
+/// bare library name, leaving something `cargo:rustc-link-lib` understands.
+fn normalize_lib_name(raw: &str) -> &str {
+    if let Some(name) = raw.strip_prefix("-l") {
+        name
+    } else if let Some(name) = raw.strip_suffix(".lib") {
+        name
+    } else {
+        raw
+    }
+}
+
+/// Lazily discover (and cache) the default [`LlvmConfig`] used by the free
+/// functions at the crate root.
+fn default_config() -> Result<&'static LlvmConfig, Error> {
+    static INSTANCE: OnceLock<LlvmConfig> = OnceLock::new();
+
+    if let Some(config) = INSTANCE.get() {
+        return Ok(config);
+    }
+
+    let config = LlvmConfig::new()?;
+    Ok(INSTANCE.get_or_init(|| config))
 }
 
 #[derive(Debug)]
@@ -138,48 +855,64 @@ impl Iterator for SpaceSeparatedStrings {
     }
 }
 
-fn run<I, O>(args: I) -> Result<Output, Error>
-where
-    I: IntoIterator<Item = O>,
-    O: AsRef<OsStr>,
-{
-    let mut command = Command::new("llvm-config");
-    command.stdin(Stdio::null());
+/// Details about an `llvm-config` invocation that exited unsuccessfully,
+/// preserved so callers can actually debug the failure instead of staring at
+/// a bare exit code.
+///
+/// Its [`Display`] output is the backquoted command line (binary followed by
+/// its arguments), then `failed`, then ` (exit N)` when the process returned
+/// an exit code, then `: ` and the trimmed stderr when that is not empty.
+#[derive(Debug, Clone)]
+pub struct CommandFailure {
+    binary: PathBuf,
+    args: Vec<String>,
+    status: ExitStatus,
+    stderr: String,
+}
 
-    for arg in args {
-        command.arg(arg);
+impl CommandFailure {
+    /// The resolved `llvm-config` binary that was invoked.
+    pub fn binary(&self) -> &Path {
+        &self.binary
     }
 
-    let output = command.output().map_err(Error::UnableToInvoke)?;
+    /// The arguments `llvm-config` was invoked with.
+    pub fn args(&self) -> &[String] {
+        &self.args
+    }
 
-    if output.status.success() {
-        Ok(output)
-    } else {
-        Err(Error::BadExitCode(output))
+    /// The exit status the command finished with.
+    pub fn status(&self) -> ExitStatus {
+        self.status
     }
-}
 
-/// Invoke `llvm-config` then transform STDOUT.
-fn map_stdout<I, O, F, T>(args: I, map: F) -> Result<T, Error>
-where
-    I: IntoIterator<Item = O>,
-    O: AsRef<OsStr>,
-    F: FnOnce(&str) -> T,
-{
-    let output = run(args)?;
-    let stdout = String::from_utf8(output.stdout)?;
-    Ok(map(stdout.trim()))
+    /// The command's captured stderr, decoded lossily.
+    pub fn stderr(&self) -> &str {
+        &self.stderr
+    }
 }
 
-/// Invoke `llvm-config` then split STDOUT by spaces.
-fn stdout_words<I, O>(args: I) -> Result<impl Iterator<Item = String>, Error>
-where
-    I: IntoIterator<Item = O>,
-    O: AsRef<OsStr>,
-{
-    let output = run(args)?;
-    let stdout = String::from_utf8(output.stdout)?;
-    Ok(SpaceSeparatedStrings::new(stdout))
+impl Display for CommandFailure {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}", self.binary.display())?;
+
+        for arg in &self.args {
+            write!(f, " {}", arg)?;
+        }
+
+        write!(f, "` failed")?;
+
+        if let Some(code) = self.status.code() {
+            write!(f, " (exit {})", code)?;
+        }
+
+        let stderr = self.stderr.trim();
+        if !stderr.is_empty() {
+            write!(f, ": {}", stderr)?;
+        }
+
+        Ok(())
+    }
 }
 
 /// An error that may occur while trying to use `llvm-config`.
@@ -190,8 +923,27 @@ pub enum Error {
     /// Unable to invoke `llvm-config`.
     UnableToInvoke(io::Error),
     /// The command ran to completion, but finished with an unsuccessful status
-    /// code (as reported by [`std::process::ExitStatus`]).
-    BadExitCode(Output),
+    /// code.
+    BadExitCode(CommandFailure),
+    /// Couldn't find an `llvm-config` binary anywhere.
+    NotFound {
+        /// The binary names (or paths) that were tried, in order.
+        tried: Vec<String>,
+    },
+    /// A requested component isn't one `llvm-config --components` knows
+    /// about.
+    UnknownComponent {
+        /// The component name that wasn't recognised.
+        name: String,
+        /// The components `llvm-config` does recognise.
+        available: Vec<String>,
+    },
+    /// `llvm-config --version` printed something that doesn't look like a
+    /// `major.minor.patch` version.
+    UnparseableVersion {
+        /// The raw, unparseable version string.
+        raw: String,
+    },
 }
 
 impl From<FromUtf8Error> for Error {
@@ -203,15 +955,34 @@ impl Display for Error {
         match self {
             Error::Utf8(_) => write!(f, "The output wasn't valid UTF-8"),
             Error::UnableToInvoke(_) => write!(f, "Unable to invoke llvm-config. Is it installed and on your $PATH?"),
-            Error::BadExitCode(output) => {
-                write!(f, "llvm-config ran unsuccessfully")?;
+            Error::BadExitCode(failure) => Display::fmt(failure, f),
+            Error::NotFound { tried } => {
+                write!(f, "Unable to find an llvm-config binary, tried: ")?;
+
+                for (i, name) in tried.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", name)?;
+                }
+
+                Ok(())
+            }
+            Error::UnknownComponent { name, available } => {
+                write!(f, "Unknown LLVM component \"{}\", expected one of: ", name)?;
 
-                if let Some(code) = output.status.code() {
-                    write!(f, " with exit code {}", code)?;
+                for (i, name) in available.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", name)?;
                 }
 
                 Ok(())
             }
+            Error::UnparseableVersion { raw } => {
+                write!(f, "Unable to parse \"{}\" as a major.minor.patch version", raw)
+            }
         }
     }
 }
@@ -222,6 +993,9 @@ impl std::error::Error for Error {
             Error::Utf8(inner) => Some(inner),
             Error::UnableToInvoke(inner) => Some(inner),
             Error::BadExitCode(_) => None,
+            Error::NotFound { .. } => None,
+            Error::UnknownComponent { .. } => None,
+            Error::UnparseableVersion { .. } => None,
         }
     }
 }
@@ -230,6 +1004,21 @@ impl std::error::Error for Error {
 mod tests {
     use super::*;
 
+    /// Build an [`ExitStatus`] with the given exit code, without spawning a
+    /// process (and therefore without depending on a shell being on `$PATH`).
+    #[cfg(unix)]
+    fn exit_status_with_code(code: i32) -> ExitStatus {
+        use std::os::unix::process::ExitStatusExt;
+        ExitStatus::from_raw(code << 8)
+    }
+
+    /// See the `unix` overload above.
+    #[cfg(windows)]
+    fn exit_status_with_code(code: i32) -> ExitStatus {
+        use std::os::windows::process::ExitStatusExt;
+        ExitStatus::from_raw(code as u32)
+    }
+
     #[test]
     fn strings_are_split_correctly() {
         let src = "aarch64 aarch64asmparser aarch64codegen aarch64desc
@@ -255,4 +1044,96 @@ mod tests {
 
         assert_eq!(got, expected);
     }
+
+    #[test]
+    fn parses_plain_version() {
+        let got = Version::parse("16.0.2").unwrap();
+        assert_eq!(got, Version::new(16, 0, 2));
+    }
+
+    #[test]
+    fn parses_version_with_suffix() {
+        let got = Version::parse("17.0.0git").unwrap();
+        assert_eq!(
+            got,
+            Version {
+                major: 17,
+                minor: 0,
+                patch: 0,
+                suffix: "git".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn versions_are_ordered() {
+        assert!(Version::new(16, 0, 0) < Version::new(17, 0, 0));
+        assert!(Version::new(16, 0, 2) > Version::new(16, 0, 1));
+    }
+
+    #[test]
+    fn classifies_common_target_triples() {
+        assert_eq!(Arch::classify("x86_64-unknown-linux-gnu"), Arch::X86_64);
+        assert_eq!(Arch::classify("aarch64-apple-darwin"), Arch::Aarch64);
+        assert_eq!(Arch::classify("armv7-unknown-linux-gnueabihf"), Arch::Arm);
+        assert_eq!(Arch::classify("riscv64gc-unknown-linux-gnu"), Arch::Riscv64);
+        assert_eq!(Arch::classify("wasm32-unknown-unknown"), Arch::Other);
+    }
+
+    #[test]
+    fn unknown_component_lists_available_components() {
+        let err = Error::UnknownComponent {
+            name: "bogus".to_string(),
+            available: vec!["core".to_string(), "x86".to_string(), "armasmparser".to_string()],
+        };
+
+        assert_eq!(
+            err.to_string(),
+            "Unknown LLVM component \"bogus\", expected one of: core, x86, armasmparser"
+        );
+    }
+
+    #[test]
+    fn linked_args_prepends_link_mode_flag() {
+        let static_cfg = LlvmConfig::at("llvm-config").with_link_mode(LinkMode::Static);
+        assert_eq!(static_cfg.linked_args(["--libs"]), vec!["--link-static", "--libs"]);
+
+        let shared_cfg = LlvmConfig::at("llvm-config").with_link_mode(LinkMode::Shared);
+        assert_eq!(shared_cfg.linked_args(["--libs"]), vec!["--link-shared", "--libs"]);
+
+        let auto_cfg = LlvmConfig::at("llvm-config");
+        assert_eq!(auto_cfg.linked_args(["--libs"]), vec!["--libs"]);
+    }
+
+    #[test]
+    fn command_failure_display_matches_documented_shape() {
+        let status = exit_status_with_code(1);
+
+        let failure = CommandFailure {
+            binary: PathBuf::from("llvm-config"),
+            args: vec!["--libs".to_string(), "core".to_string()],
+            status,
+            stderr: "no such component: core\n".to_string(),
+        };
+
+        assert_eq!(
+            failure.to_string(),
+            "`llvm-config --libs core` failed (exit 1): no such component: core"
+        );
+    }
+
+    #[test]
+    fn normalize_lib_name_strips_platform_decorations() {
+        assert_eq!(normalize_lib_name("-lLLVMCore"), "LLVMCore");
+        assert_eq!(normalize_lib_name("LLVMCore.lib"), "LLVMCore");
+        assert_eq!(normalize_lib_name("pthread"), "pthread");
+    }
+
+    #[test]
+    fn default_candidate_names_start_with_unsuffixed_binary() {
+        let names = default_candidate_names();
+        assert_eq!(names[0], "llvm-config");
+        assert_eq!(names[1], format!("llvm-config-{}", LlvmConfig::MAX_VERSION));
+        assert_eq!(names.last().unwrap(), &format!("llvm-config-{}", LlvmConfig::MIN_VERSION));
+    }
 }